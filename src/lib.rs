@@ -4,7 +4,7 @@ use std::ffi::OsStr;
 use std::fmt::{Debug, Display};
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, RwLock, Weak};
 
@@ -24,12 +24,139 @@ fn cold() {}
 const STACK_STR_SIZE: usize = 20;
 
 enum StringRef {
+    // the second field is the `Symbol` index for this string, once one has been assigned
+    Heap(Weak<TableString>, Option<u32>),
+    Static(&'static str, Option<u32>),
+}
+
+// number of independent shards the interning table is split into; a lookup or insert only
+// ever locks one of them, so unrelated strings never contend with each other
+const TABLE_SHARDS: usize = 64;
+
+lazy_static::lazy_static! {
+    static ref TABLES: [RwLock<RawTable<StringRef>>; TABLE_SHARDS] =
+        std::array::from_fn(|_| RwLock::new(RawTable::new()));
+    // append-only: indices handed out as `Symbol`s must never move or be reused
+    static ref SYMBOLS: RwLock<Vec<StringRepr>> = RwLock::new(Vec::new());
+}
+
+// picks a shard from the top bits of the hash, leaving the lower bits hashbrown uses for
+// in-table bucket placement untouched
+fn shard_for(hash: u64) -> usize {
+    (hash >> (u64::BITS - TABLE_SHARDS.ilog2())) as usize
+}
+
+// number of live entries in the table, across every shard: every static entry, plus every heap
+// entry that still has a live `InternedString` or `Symbol`. Strings of at most
+// `STACK_STR_SIZE` bytes are stored inline and never enter the table, so they are not counted.
+pub fn interned_count() -> usize {
+    TABLES
+        .iter()
+        .map(|shard| {
+            let guard = shard.read().unwrap();
+            unsafe { guard.iter() }
+                .filter(|bucket| match unsafe { bucket.as_ref() } {
+                    StringRef::Heap(w, _) => w.strong_count() > 0,
+                    StringRef::Static(..) => true,
+                })
+                .count()
+        })
+        .sum()
+}
+
+// visits every currently-live string in the table, upgrading weak entries as it goes; each
+// shard's read lock is taken exactly once. Strings stored inline (at most `STACK_STR_SIZE`
+// bytes) are not in the table and are not visited.
+pub fn for_each_live(mut f: impl FnMut(&str)) {
+    for shard in TABLES.iter() {
+        let guard = shard.read().unwrap();
+        for bucket in unsafe { guard.iter() } {
+            match unsafe { bucket.as_ref() } {
+                StringRef::Heap(w, _) => {
+                    if let Some(ts) = Weak::upgrade(w) {
+                        f(ts.0.as_str());
+                    }
+                }
+                StringRef::Static(s, _) => f(s),
+            }
+        }
+    }
+}
+
+// walks every shard once, erasing `StringRef::Heap` entries whose last `InternedString` has
+// already been dropped; reclamation otherwise only happens one entry at a time, in
+// `TableString`'s `Drop` impl, so this is useful to run after a burst of churn
+pub fn vacuum() {
+    for shard in TABLES.iter() {
+        let mut guard = shard.write().unwrap();
+        let dead: Vec<_> = unsafe { guard.iter() }
+            .filter(|bucket| {
+                matches!(unsafe { bucket.as_ref() }, StringRef::Heap(w, _) if w.strong_count() == 0)
+            })
+            .collect();
+        for bucket in dead {
+            unsafe { guard.erase(bucket) };
+        }
+    }
+}
+
+// direct-mapped per-thread cache in front of `TABLES`: a hit upgrades a `Weak` (an `Arc` bump at
+// worst) without ever touching the global lock. Bounded and overwritten on collision, and holds
+// only `Weak` references, so it can't pin a dropped string's allocation alive forever.
+#[cfg(feature = "thread-cache")]
+const THREAD_CACHE_SIZE: usize = 64;
+
+// what the thread-local cache actually stores: a `Heap` entry holds a `Weak`, not the `Arc` that
+// `StringRepr::Heap` does, so the cache can't keep a string alive on its own — it must agree with
+// `TABLES` about whether a string is still live, or `vacuum`/`for_each_live` couldn't be trusted
+#[cfg(feature = "thread-cache")]
+#[derive(Clone)]
+enum ThreadCacheRepr {
     Heap(Weak<TableString>),
+    Stack(ArrayVec<[u8; STACK_STR_SIZE]>),
     Static(&'static str),
 }
 
-lazy_static::lazy_static! {
-    static ref TABLE: RwLock<RawTable<StringRef>> = RwLock::new(RawTable::new());
+#[cfg(feature = "thread-cache")]
+thread_local! {
+    static THREAD_CACHE: std::cell::RefCell<[Option<(u64, ThreadCacheRepr)>; THREAD_CACHE_SIZE]> =
+        std::cell::RefCell::new(std::array::from_fn(|_| None));
+}
+
+#[cfg(feature = "thread-cache")]
+fn thread_cache_get<S: Display>(hash: u64, s: &S) -> Option<StringRepr> {
+    THREAD_CACHE.with_borrow(|cache| {
+        let (h, repr) = cache[hash as usize & (THREAD_CACHE_SIZE - 1)].as_ref()?;
+        if *h != hash {
+            return None;
+        }
+        // a `Heap` slot whose last `InternedString` has since been dropped is a miss, not a hit:
+        // falling through to the slow path re-interns it rather than resurrecting a dead entry
+        let repr = match repr {
+            ThreadCacheRepr::Heap(w) => StringRepr::Heap(Weak::upgrade(w)?),
+            ThreadCacheRepr::Stack(s) => StringRepr::Stack(s.clone()),
+            ThreadCacheRepr::Static(s) => StringRepr::Static(s),
+        };
+        DisplayEq::eq(s, repr.as_str()).then_some(repr)
+    })
+}
+
+#[cfg(feature = "thread-cache")]
+fn thread_cache_put(hash: u64, repr: StringRepr) {
+    let repr = match repr {
+        StringRepr::Heap(ts) => ThreadCacheRepr::Heap(Arc::downgrade(&ts)),
+        StringRepr::Stack(s) => ThreadCacheRepr::Stack(s),
+        StringRepr::Static(s) => ThreadCacheRepr::Static(s),
+    };
+    THREAD_CACHE.with_borrow_mut(|cache| {
+        cache[hash as usize & (THREAD_CACHE_SIZE - 1)] = Some((hash, repr));
+    });
+}
+
+// clears this thread's fast-path cache
+#[cfg(feature = "thread-cache")]
+pub fn flush_thread_cache() {
+    THREAD_CACHE.with_borrow_mut(|cache| *cache = std::array::from_fn(|_| None));
 }
 
 type TableHasher = ahash::AHasher;
@@ -96,7 +223,7 @@ impl Drop for TableString {
     fn drop(&mut self) {
         let hash = DisplayHasher::<TableHasher>::hash(&self.0);
         let eq = |s: &StringRef| {
-            if let StringRef::Heap(s) = s
+            if let StringRef::Heap(s, _) = s
                 && s.strong_count() == 0
             {
                 true
@@ -104,7 +231,7 @@ impl Drop for TableString {
                 false
             }
         };
-        let mut guard = TABLE.write().unwrap();
+        let mut guard = TABLES[shard_for(hash)].write().unwrap();
         if !guard.erase_entry(hash, eq) {
             cold();
             let hash = TableHasher::default().finish();
@@ -164,54 +291,69 @@ impl InternedString {
         if let Some(stack) = stack {
             return Self(StringRepr::Stack(stack));
         }
+
+        #[cfg(feature = "thread-cache")]
+        if let Some(repr) = thread_cache_get(hash, &s) {
+            return Self(repr);
+        }
+
+        let result = Self::intern_slow(s, hash);
+
+        #[cfg(feature = "thread-cache")]
+        thread_cache_put(hash, result.0.clone());
+
+        result
+    }
+
+    fn intern_slow<S: Display + Into<String>>(s: S, hash: u64) -> Self {
         let eq = |ts: &StringRef| match ts {
-            StringRef::Heap(ts) => {
+            StringRef::Heap(ts, _) => {
                 if let Some(ts) = Weak::upgrade(ts) {
                     DisplayEq::eq(&s, ts.0.as_str())
                 } else {
                     false
                 }
             }
-            StringRef::Static(ts) => DisplayEq::eq(&s, *ts),
+            StringRef::Static(ts, _) => DisplayEq::eq(&s, *ts),
         };
         // READ section
         {
-            match TABLE.read().unwrap().get(hash, eq) {
-                Some(StringRef::Heap(ts)) => {
+            match TABLES[shard_for(hash)].read().unwrap().get(hash, eq) {
+                Some(StringRef::Heap(ts, _)) => {
                     if let Some(ts) = Weak::upgrade(ts) {
                         return Self(StringRepr::Heap(ts));
                     }
                 }
-                Some(StringRef::Static(ts)) => return Self(StringRepr::Static(*ts)),
+                Some(StringRef::Static(ts, _)) => return Self(StringRepr::Static(*ts)),
                 _ => (),
             }
         }
         // WRITE section
         {
-            let mut guard = TABLE.write().unwrap();
+            let mut guard = TABLES[shard_for(hash)].write().unwrap();
             // RACE CONDITION: check again if it exists
             if let Some(ts) = guard.get_mut(hash, eq) {
                 cold(); // unlikely
                 match ts {
-                    StringRef::Heap(ts) => {
+                    StringRef::Heap(ts, _) => {
                         if let Some(ts) = Weak::upgrade(ts) {
                             return Self(StringRepr::Heap(ts));
                         }
                     }
-                    StringRef::Static(ts) => return Self(StringRepr::Static(*ts)),
+                    StringRef::Static(ts, _) => return Self(StringRepr::Static(*ts)),
                 }
             }
             // we need to create it
             let res = Arc::new(TableString(s.into()));
-            guard.insert(hash, StringRef::Heap(Arc::downgrade(&res)), |ts| {
+            guard.insert(hash, StringRef::Heap(Arc::downgrade(&res), None), |ts| {
                 let mut hasher = TableHasher::default();
                 match ts {
-                    StringRef::Heap(ts) => {
+                    StringRef::Heap(ts, _) => {
                         if let Some(ts) = Weak::upgrade(ts) {
                             hasher.write(ts.0.as_bytes())
                         }
                     }
-                    StringRef::Static(ts) => hasher.write(ts.as_bytes()),
+                    StringRef::Static(ts, _) => hasher.write(ts.as_bytes()),
                 }
                 hasher.finish()
             });
@@ -244,35 +386,42 @@ impl InternedString {
             return Self(StringRepr::Stack(stack));
         }
         let eq = |ts: &StringRef| match ts {
-            StringRef::Heap(ts) => {
+            StringRef::Heap(ts, _) => {
                 if let Some(ts) = Weak::upgrade(ts) {
                     DisplayEq::eq(&s, ts.0.as_str())
                 } else {
                     false
                 }
             }
-            StringRef::Static(ts) => DisplayEq::eq(&s, *ts),
+            StringRef::Static(ts, _) => DisplayEq::eq(&s, *ts),
         };
-        let mut guard = TABLE.write().unwrap();
+        let mut guard = TABLES[shard_for(hash)].write().unwrap();
 
-        // check if it exists
+        // check if it exists; if it's already `Static`, return the table's canonical pointer
+        // rather than the caller's `s`, so that two `intern_static` calls with distinct but
+        // byte-identical literals are guaranteed `Arc`/`ptr::eq`-equal without relying on the
+        // compiler having merged the two literals into one allocation
         if let Some(ts) = guard.get_mut(hash, eq) {
-            if !matches!(ts, StringRef::Static(_)) {
-                *ts = StringRef::Static(s);
+            match ts {
+                StringRef::Static(existing, _) => return Self(StringRepr::Static(*existing)),
+                StringRef::Heap(_, idx) => {
+                    let idx = *idx;
+                    *ts = StringRef::Static(s, idx);
+                    return Self(StringRepr::Static(s));
+                }
             }
-            return Self(StringRepr::Static(s));
         }
 
         // we need to create it
-        guard.insert(hash, StringRef::Static(s), |ts| {
+        guard.insert(hash, StringRef::Static(s, None), |ts| {
             let mut hasher = TableHasher::default();
             match ts {
-                StringRef::Heap(ts) => {
+                StringRef::Heap(ts, _) => {
                     if let Some(ts) = Weak::upgrade(ts) {
                         hasher.write(ts.0.as_bytes())
                     }
                 }
-                StringRef::Static(ts) => hasher.write(ts.as_bytes()),
+                StringRef::Static(ts, _) => hasher.write(ts.as_bytes()),
             }
             hasher.finish()
         });
@@ -280,6 +429,20 @@ impl InternedString {
     }
 }
 
+// Interns a string literal once per call site instead of on every call: the first visit pays
+// for `intern_static`'s table lookup and stashes the result in a `OnceLock`, later visits just
+// clone it out. A later runtime `InternedString::intern(s)` of the same bytes still upgrades
+// to this static entry, same as calling `intern_static` directly.
+#[macro_export]
+macro_rules! intern {
+    ($lit:literal) => {{
+        static CACHE: ::std::sync::OnceLock<$crate::InternedString> = ::std::sync::OnceLock::new();
+        CACHE
+            .get_or_init(|| $crate::InternedString::intern_static($lit))
+            .clone()
+    }};
+}
+
 impl Deref for InternedString {
     type Target = str;
     fn deref(&self) -> &Self::Target {
@@ -403,3 +566,297 @@ impl PartialEq<str> for InternedString {
         self.0.as_str().eq(other)
     }
 }
+
+// `Symbol` trades `InternedString`'s weak-reference reclamation for a plain `u32` handle:
+// once a string has been interned as a `Symbol` its table entry is pinned alive for the rest
+// of the process, so the index assigned to it is permanent and cheap to copy around.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn intern<S: Display + Into<String>>(s: S) -> Self {
+        let hash = DisplayHasher::<TableHasher>::hash(&s);
+        let eq = |ts: &StringRef| match ts {
+            StringRef::Heap(ts, _) => {
+                if let Some(ts) = Weak::upgrade(ts) {
+                    DisplayEq::eq(&s, ts.0.as_str())
+                } else {
+                    false
+                }
+            }
+            StringRef::Static(ts, _) => DisplayEq::eq(&s, *ts),
+        };
+
+        let mut guard = TABLES[shard_for(hash)].write().unwrap();
+        if let Some(ts) = guard.get_mut(hash, eq) {
+            let existing = match ts {
+                StringRef::Heap(_, idx) | StringRef::Static(_, idx) => *idx,
+            };
+            if let Some(i) = existing {
+                return Self(i);
+            }
+            let repr = match ts {
+                StringRef::Heap(ts, _) => Weak::upgrade(ts).map(StringRepr::Heap),
+                StringRef::Static(ts, _) => Some(StringRepr::Static(*ts)),
+            };
+            if let Some(repr) = repr {
+                let mut symbols = SYMBOLS.write().unwrap();
+                let i = symbols.len() as u32;
+                symbols.push(repr);
+                match ts {
+                    StringRef::Heap(_, idx) | StringRef::Static(_, idx) => *idx = Some(i),
+                }
+                return Self(i);
+            }
+            // the entry is a `Weak` in the middle of being dropped; fall through and
+            // intern fresh below, same as the race tolerated by `InternedString::intern`
+            cold();
+        }
+
+        // we need to create it
+        let res = Arc::new(TableString(s.into()));
+        let mut symbols = SYMBOLS.write().unwrap();
+        let i = symbols.len() as u32;
+        symbols.push(StringRepr::Heap(res.clone()));
+        guard.insert(hash, StringRef::Heap(Arc::downgrade(&res), Some(i)), |ts| {
+            let mut hasher = TableHasher::default();
+            match ts {
+                StringRef::Heap(ts, _) => {
+                    if let Some(ts) = Weak::upgrade(ts) {
+                        hasher.write(ts.0.as_bytes())
+                    }
+                }
+                StringRef::Static(ts, _) => hasher.write(ts.as_bytes()),
+            }
+            hasher.finish()
+        });
+        Self(i)
+    }
+
+    pub fn resolve(&self) -> InternedString {
+        let guard = SYMBOLS.read().unwrap();
+        InternedString(guard[self.0 as usize].clone())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        let guard = SYMBOLS.read().unwrap();
+        let s = guard[self.0 as usize].as_str();
+        // SAFETY: `SYMBOLS` is append-only and each entry keeps its string alive for the
+        // remainder of the process (a strong `Arc` for `Heap`, or `&'static` already), so the
+        // slice is valid for `'static` even after this read guard is dropped.
+        unsafe { std::mem::transmute::<&str, &'static str>(s) }
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        if self == other {
+            std::cmp::Ordering::Equal
+        } else {
+            self.as_str().cmp(other.as_str())
+        }
+    }
+}
+
+// `InternedPath` shares `InternedString`'s table instead of growing a path-specific one: a
+// path's UTF-8 bytes are interned like any other string, so a path and a string with the same
+// bytes reuse the same allocation.
+pub struct InternedPath(InternedString);
+
+impl InternedPath {
+    // panics if `path` is not valid UTF-8; use `try_intern` to handle that case instead
+    pub fn intern<P: AsRef<Path>>(path: P) -> Self {
+        Self::try_intern(path).expect("InternedPath::intern: path is not valid UTF-8")
+    }
+
+    // `None` if `path` is not valid UTF-8, rather than lossily mangling it the way
+    // `Path::display` would (replacing invalid bytes with `U+FFFD`, silently changing the path)
+    pub fn try_intern<P: AsRef<Path>>(path: P) -> Option<Self> {
+        Some(Self(InternedString::intern(path.as_ref().to_str()?)))
+    }
+
+    pub fn as_interned_str(&self) -> InternedString {
+        self.0.clone()
+    }
+}
+
+impl InternedString {
+    pub fn as_interned_path(&self) -> InternedPath {
+        InternedPath(self.clone())
+    }
+}
+
+impl Deref for InternedPath {
+    type Target = Path;
+    fn deref(&self) -> &Self::Target {
+        Path::new(&*self.0)
+    }
+}
+
+impl AsRef<Path> for InternedPath {
+    fn as_ref(&self) -> &Path {
+        self
+    }
+}
+
+impl AsRef<OsStr> for InternedPath {
+    fn as_ref(&self) -> &OsStr {
+        (*self.0).as_ref()
+    }
+}
+
+impl Borrow<Path> for InternedPath {
+    fn borrow(&self) -> &Path {
+        self
+    }
+}
+
+impl Clone for InternedPath {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl Debug for InternedPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl Default for InternedPath {
+    fn default() -> Self {
+        Self(InternedString::default())
+    }
+}
+
+// `PartialEq`/`Hash`/`Ord` must agree with the `Borrow<Path>` impl above, so they compare and
+// hash through `Path` rather than `str`/`InternedString` (which differ from `Path` on things
+// like repeated separators) — otherwise a `HashMap`/`BTreeMap` keyed by `InternedPath` would
+// silently miss entries when looked up via `&Path`.
+impl PartialEq for InternedPath {
+    fn eq(&self, other: &Self) -> bool {
+        // cheap same-interned-string fast path; falls back to real `Path` equality since two
+        // different interned strings can still be the same `Path`
+        self.0 == other.0 || **self == **other
+    }
+}
+
+impl Eq for InternedPath {}
+
+impl PartialOrd for InternedPath {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for InternedPath {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        if self == other {
+            std::cmp::Ordering::Equal
+        } else {
+            (**self).cmp(&**other)
+        }
+    }
+}
+
+impl Hash for InternedPath {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl From<&Path> for InternedPath {
+    fn from(value: &Path) -> Self {
+        Self::intern(value)
+    }
+}
+
+impl From<PathBuf> for InternedPath {
+    fn from(value: PathBuf) -> Self {
+        Self::intern(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Barrier;
+    use std::thread;
+
+    use super::InternedString;
+
+    #[test]
+    fn concurrent_intern_and_drop_across_shards() {
+        const THREADS: usize = 16;
+        const STRINGS: usize = 200;
+
+        let barrier = Barrier::new(THREADS);
+        thread::scope(|scope| {
+            for t in 0..THREADS {
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    barrier.wait();
+                    for round in 0..50 {
+                        for i in 0..STRINGS {
+                            // half the strings are shared across threads, half are
+                            // thread-local, so both the hit path and the insert/erase
+                            // race get exercised under sharding; every string is longer than
+                            // `STACK_STR_SIZE`, so none of them is stored inline
+                            let s = if i % 2 == 0 {
+                                format!("shared-shard-stress-string-{i}-{round}")
+                            } else {
+                                format!("thread-local-shard-stress-string-{t}-{i}-{round}")
+                            };
+                            let interned = InternedString::intern(s.clone());
+                            assert_eq!(&*interned, s.as_str());
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn intern_macro_shares_one_static_entry_per_call_site() {
+        let a = crate::intern!("macro-interned-literal");
+        let b = crate::intern!("macro-interned-literal");
+        assert_eq!(a, b);
+
+        let runtime = InternedString::intern("macro-interned-literal".to_string());
+        assert_eq!(a, runtime);
+    }
+
+    #[test]
+    fn vacuum_reclaims_dropped_strings() {
+        let marker = "vacuum-test-marker-unique-xyz".to_string();
+        let interned = InternedString::intern(marker.clone());
+
+        let mut seen = false;
+        super::for_each_live(|s| seen |= s == marker);
+        assert!(seen);
+
+        drop(interned);
+        super::vacuum();
+
+        let mut seen_after_vacuum = false;
+        super::for_each_live(|s| seen_after_vacuum |= s == marker);
+        assert!(!seen_after_vacuum);
+    }
+}
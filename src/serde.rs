@@ -1,7 +1,9 @@
+use std::path::Path;
+
 use serde::de::{Error, Unexpected, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::InternedString;
+use crate::{InternedPath, InternedString};
 
 impl Serialize for InternedString {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -66,3 +68,44 @@ impl<'de> Visitor<'de> for InternedStringVisitor {
         }
     }
 }
+
+impl Serialize for InternedPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for InternedPath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_string(InternedPathVisitor)
+    }
+}
+
+struct InternedPathVisitor;
+impl<'de> Visitor<'de> for InternedPathVisitor {
+    type Value = InternedPath;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a path")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(InternedPath::intern(Path::new(v)))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(InternedPath::intern(Path::new(&v)))
+    }
+}